@@ -0,0 +1,45 @@
+use axum::http::StatusCode;
+use thiserror::Error;
+
+pub type PentaractResult<T> = Result<T, PentaractError>;
+
+#[derive(Debug, Error)]
+pub enum PentaractError {
+    #[error("{0} not found")]
+    NotFound(String),
+
+    #[error("permission denied")]
+    PermissionDenied,
+
+    #[error("invalid path")]
+    InvalidPath,
+
+    /// A resumable upload session's `PATCH` arrived with an `Upload-Offset`
+    /// that doesn't match the offset the server has on record.
+    #[error("Upload-Offset does not match the session's current offset")]
+    UploadOffsetMismatch,
+
+    #[error("storage backend error: {0}")]
+    StorageBackend(String),
+
+    #[error(transparent)]
+    Db(#[from] sqlx::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl From<PentaractError> for (StatusCode, String) {
+    fn from(error: PentaractError) -> Self {
+        let status = match &error {
+            PentaractError::NotFound(_) => StatusCode::NOT_FOUND,
+            PentaractError::PermissionDenied => StatusCode::FORBIDDEN,
+            PentaractError::InvalidPath => StatusCode::BAD_REQUEST,
+            PentaractError::UploadOffsetMismatch => StatusCode::CONFLICT,
+            PentaractError::StorageBackend(_) | PentaractError::Db(_) | PentaractError::Io(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        (status, error.to_string())
+    }
+}
@@ -0,0 +1,14 @@
+use uuid::Uuid;
+
+/// One row per unique `(storage_id, content_digest)` pair currently stored.
+/// `ref_count` is the number of live [`super::files::InFile`] records
+/// pointing at this content; the row and the backing blob are deleted once
+/// it drops to zero.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ContentIndexEntry {
+    pub id: Uuid,
+    pub storage_id: Uuid,
+    pub content_digest: String,
+    pub size: i64,
+    pub ref_count: i64,
+}
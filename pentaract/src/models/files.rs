@@ -0,0 +1,37 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+/// A logical file record created by `upload`/`upload_to`. The bytes it
+/// points at live in the content-addressed blob named by `content_digest`
+/// (see [`crate::models::content_index`]), so several `InFile`s - even
+/// across unrelated uploads - can share one copy of identical content.
+#[derive(Debug, Clone, Serialize)]
+pub struct InFile {
+    pub path: String,
+    pub size: i64,
+    pub storage_id: Uuid,
+    pub content_digest: String,
+    pub content_type: Option<String>,
+}
+
+impl InFile {
+    pub fn new(
+        path: String,
+        size: i64,
+        storage_id: Uuid,
+        content_digest: String,
+        content_type: Option<String>,
+    ) -> Self {
+        Self { path, size, storage_id, content_digest, content_type }
+    }
+}
+
+/// One entry of a directory listing, as returned by `list_dir`/`search` and
+/// served straight to HTTP clients as JSON and to SFTP clients as `Name`
+/// records.
+#[derive(Debug, Clone, Serialize)]
+pub struct FsEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: i64,
+}
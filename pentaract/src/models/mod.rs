@@ -0,0 +1,4 @@
+pub mod content_index;
+pub mod files;
+pub mod upload_jobs;
+pub mod upload_sessions;
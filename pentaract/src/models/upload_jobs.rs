@@ -0,0 +1,32 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum UploadJobStatus {
+    Queued,
+    Uploading,
+    Done,
+    Failed,
+}
+
+impl UploadJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Uploading => "uploading",
+            Self::Done => "done",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Progress record for an upload started with `?async=true` (or
+/// `X-Upload-Async`), polled via `GET /upload_jobs/{id}`.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct UploadJob {
+    pub id: Uuid,
+    pub status: UploadJobStatus,
+    pub progress_bytes: i64,
+    pub error: Option<String>,
+}
@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use uuid::Uuid;
+
+/// A resumable tus-style upload in progress: `offset` is how many bytes of
+/// `temp_file_path` the server has durably stored so far, out of the
+/// client-declared `total_size`.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UploadSession {
+    pub id: Uuid,
+    pub storage_id: Uuid,
+    pub path: String,
+    pub total_size: i64,
+    pub offset: i64,
+    pub temp_file_path: String,
+    pub owner_id: Uuid,
+}
+
+impl UploadSession {
+    pub fn temp_file_path(&self) -> PathBuf {
+        PathBuf::from(&self.temp_file_path)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.offset >= self.total_size
+    }
+}
@@ -1,16 +1,18 @@
 use std::{path::Path, sync::Arc};
 
 use axum::{
-    body::Full,
+    body::StreamBody,
     extract::{DefaultBodyLimit, Multipart, Path as RoutePath, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     middleware,
     response::{AppendHeaders, IntoResponse, Response},
-    routing::{get, post},
+    routing::{get, head, post},
     Extension, Json, Router,
 };
 use reqwest::header;
-use tokio::io::AsyncWriteExt;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_stream::wrappers::ReceiverStream;
 use tokio_util::bytes::Bytes;
 use uuid::Uuid;
 
@@ -25,14 +27,63 @@ use crate::{
     services::files::FilesService,
 };
 
+/// Body for `POST /upload_session`: declares where the finished upload will
+/// live and how large it will be, so the server can track progress.
+#[derive(Deserialize)]
+struct CreateUploadSessionParams {
+    path: String,
+    size: i64,
+}
+
+/// Response for `POST /upload_session`.
+#[derive(Serialize)]
+struct UploadSessionCreated {
+    id: Uuid,
+}
+
+/// Header carrying the client's notion of how many bytes have been sent so
+/// far for a resumable upload session.
+const UPLOAD_OFFSET: &str = "upload-offset";
+
+/// Header that opts `upload`/`upload_to` into async mode, as an alternative
+/// to the `?async=true` query parameter.
+const UPLOAD_ASYNC_HEADER: &str = "x-upload-async";
+
+/// Query parameters accepted by `upload`/`upload_to`.
+#[derive(Deserialize)]
+struct UploadQuery {
+    #[serde(rename = "async")]
+    async_: Option<bool>,
+}
+
+/// Response for an upload accepted in async mode.
+#[derive(Serialize)]
+struct UploadJobCreated {
+    id: Uuid,
+}
+
 pub struct FilesRouter;
 
+/// Outcome of parsing a `Range` header against a file's total size.
+enum RangeError {
+    /// The header uses a form we don't support (e.g. multiple ranges).
+    Unsupported,
+    /// The requested range falls outside `0..total`.
+    Unsatisfiable,
+}
+
 impl FilesRouter {
     pub fn get_router(state: Arc<AppState>) -> Router<Arc<AppState>, axum::body::Body> {
         Router::new()
             .route("/create_folder", post(Self::create_folder))
             .route("/upload", post(Self::upload))
             .route("/upload_to", post(Self::upload_to))
+            .route("/upload_session", post(Self::create_upload_session))
+            .route(
+                "/upload_session/:session_id",
+                head(Self::upload_session_status).patch(Self::upload_session_patch),
+            )
+            .route("/upload_jobs/:job_id", get(Self::upload_job_status))
             .route("/*path", get(Self::dynamic_get).delete(Self::delete))
             .layer(DefaultBodyLimit::disable())
             .route_layer(middleware::from_fn_with_state(
@@ -47,11 +98,19 @@ impl FilesRouter {
         Extension(user): Extension<AuthUser>,
         RoutePath((storage_id, path)): RoutePath<(Uuid, String)>,
         query: Query<SearchQuery>,
+        headers: HeaderMap,
     ) -> impl IntoResponse {
         let (root_path, path) = path.split_once("/").unwrap_or((&path, ""));
         match root_path {
             "tree" => Self::tree(state, user, storage_id, path).await,
-            "download" => Self::download(state, user, storage_id, path).await,
+            "download" => {
+                let range = headers
+                    .get(header::RANGE)
+                    .and_then(|value| value.to_str().ok())
+                    .map(|value| value.to_owned());
+                let inline = query.0.disposition.as_deref() == Some("inline");
+                Self::download(state, user, storage_id, path, range, inline).await
+            }
             "search" => {
                 if let Some(search_path) = query.0.search_path {
                     Self::search(state, user, storage_id, path, &search_path).await
@@ -82,8 +141,12 @@ impl FilesRouter {
         State(state): State<Arc<AppState>>,
         Extension(user): Extension<AuthUser>,
         RoutePath(storage_id): RoutePath<Uuid>,
+        Query(query): Query<UploadQuery>,
+        headers: HeaderMap,
         mut multipart: Multipart,
-    ) -> Result<StatusCode, (StatusCode, String)> {
+    ) -> Result<Response, (StatusCode, String)> {
+        let is_async = query.async_.unwrap_or(false) || headers.contains_key(UPLOAD_ASYNC_HEADER);
+
         // Ensure temp directory exists
         let temp_dir = &state.config.temp_dir;
         tokio::fs::create_dir_all(temp_dir).await.map_err(|e| {
@@ -97,8 +160,9 @@ impl FilesRouter {
         let temp_file_path = temp_dir.join(format!("upload_{}.tmp", Uuid::new_v4()));
 
         // parsing - stream file to disk instead of loading into memory
-        let (path, size) = {
-            let (mut filename, mut path, mut size) = (None, None, 0i64);
+        let (path, size, digest, content_type) = {
+            let (mut filename, mut path, mut size, mut content_type) = (None, None, 0i64, None);
+            let mut hasher = blake3::Hasher::new();
 
             while let Some(mut field) = multipart.next_field().await.map_err(|e| {
                 (StatusCode::BAD_REQUEST, format!("Multipart error: {}", e))
@@ -109,6 +173,7 @@ impl FilesRouter {
                 match name.as_str() {
                     "file" => {
                         filename = Some(field_filename);
+                        content_type = field.content_type().map(|ct| ct.to_string());
                         // Stream file data to disk
                         let mut temp_file =
                             tokio::fs::File::create(&temp_file_path).await.map_err(|e| {
@@ -122,6 +187,7 @@ impl FilesRouter {
                             (StatusCode::BAD_REQUEST, format!("Failed to read chunk: {}", e))
                         })? {
                             size += chunk.len() as i64;
+                            hasher.update(&chunk);
                             temp_file.write_all(&chunk).await.map_err(|e| {
                                 (
                                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -154,14 +220,26 @@ impl FilesRouter {
             let path = path
                 .ok_or((StatusCode::BAD_REQUEST, "path field is required".to_owned()))
                 .map(|path| Self::construct_path(&path, &filename))??;
-            (path, size)
+            (path, size, hasher.finalize().to_hex().to_string(), content_type)
         };
 
-        let in_file = InFile::new(path, size, storage_id);
+        let in_file = InFile::new(path, size, storage_id, digest, content_type);
+        let service = FilesService::new(&state.db, state.tx.clone());
 
-        let result = FilesService::new(&state.db, state.tx.clone())
-            .upload_anyway(in_file, temp_file_path.clone(), &user)
-            .await;
+        if is_async {
+            let job = service.enqueue_upload_job(in_file, temp_file_path.clone(), &user).await;
+
+            // The worker owns the temp file once it's queued; if we never
+            // managed to queue it, nothing else will clean it up.
+            if job.is_err() {
+                let _ = tokio::fs::remove_file(&temp_file_path).await;
+            }
+
+            let id = job.map_err(<(StatusCode, String)>::from)?;
+            return Ok((StatusCode::ACCEPTED, Json(UploadJobCreated { id })).into_response());
+        }
+
+        let result = service.upload_anyway(in_file, temp_file_path.clone(), &user).await;
 
         // Clean up temp file on error (success cleanup is handled by storage manager)
         if result.is_err() {
@@ -169,15 +247,19 @@ impl FilesRouter {
         }
 
         result?;
-        Ok(StatusCode::CREATED)
+        Ok(StatusCode::CREATED.into_response())
     }
 
     async fn upload_to(
         State(state): State<Arc<AppState>>,
         Extension(user): Extension<AuthUser>,
         RoutePath(storage_id): RoutePath<Uuid>,
+        Query(query): Query<UploadQuery>,
+        headers: HeaderMap,
         mut multipart: Multipart,
-    ) -> Result<StatusCode, (StatusCode, String)> {
+    ) -> Result<Response, (StatusCode, String)> {
+        let is_async = query.async_.unwrap_or(false) || headers.contains_key(UPLOAD_ASYNC_HEADER);
+
         // Ensure temp directory exists
         let temp_dir = &state.config.temp_dir;
         tokio::fs::create_dir_all(temp_dir).await.map_err(|e| {
@@ -192,7 +274,8 @@ impl FilesRouter {
 
         // parsing and validating schema - stream file to disk
         let in_schema = {
-            let (mut path, mut size) = (None, 0i64);
+            let (mut path, mut size, mut content_type) = (None, 0i64, None);
+            let mut hasher = blake3::Hasher::new();
 
             while let Some(mut field) = multipart.next_field().await.map_err(|e| {
                 (StatusCode::BAD_REQUEST, format!("Multipart error: {}", e))
@@ -201,6 +284,7 @@ impl FilesRouter {
 
                 match name.as_str() {
                     "file" => {
+                        content_type = field.content_type().map(|ct| ct.to_string());
                         // Stream file data to disk
                         let mut temp_file =
                             tokio::fs::File::create(&temp_file_path).await.map_err(|e| {
@@ -214,6 +298,7 @@ impl FilesRouter {
                             (StatusCode::BAD_REQUEST, format!("Failed to read chunk: {}", e))
                         })? {
                             size += chunk.len() as i64;
+                            hasher.update(&chunk);
                             temp_file.write_all(&chunk).await.map_err(|e| {
                                 (
                                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -241,6 +326,7 @@ impl FilesRouter {
                 }
             }
 
+            let digest = hasher.finalize().to_hex().to_string();
             let path =
                 path.ok_or((StatusCode::BAD_REQUEST, "path field is required".to_owned()))?;
 
@@ -248,13 +334,26 @@ impl FilesRouter {
                 return Err((StatusCode::BAD_REQUEST, "file field is required".to_owned()));
             }
 
-            InFileSchema::new(storage_id, path, size, temp_file_path.clone())
+            InFileSchema::new(storage_id, path, size, temp_file_path.clone(), digest, content_type)
         };
 
+        let service = FilesService::new(&state.db, state.tx.clone());
+
+        if is_async {
+            let job = service.enqueue_upload_to_job(in_schema, &user).await;
+
+            // The worker owns the temp file once it's queued; if we never
+            // managed to queue it, nothing else will clean it up.
+            if job.is_err() {
+                let _ = tokio::fs::remove_file(&temp_file_path).await;
+            }
+
+            let id = job.map_err(<(StatusCode, String)>::from)?;
+            return Ok((StatusCode::ACCEPTED, Json(UploadJobCreated { id })).into_response());
+        }
+
         // do all other stuff
-        let result = FilesService::new(&state.db, state.tx.clone())
-            .upload_to(in_schema, &user)
-            .await;
+        let result = service.upload_to(in_schema, &user).await;
 
         // Clean up temp file on error (success cleanup is handled by storage manager)
         if result.is_err() {
@@ -262,7 +361,119 @@ impl FilesRouter {
         }
 
         result?;
-        Ok(StatusCode::CREATED)
+        Ok(StatusCode::CREATED.into_response())
+    }
+
+    /// `GET /upload_jobs/{id}` — current status/progress of an upload
+    /// enqueued via `?async=true` (or the `X-Upload-Async` header).
+    async fn upload_job_status(
+        State(state): State<Arc<AppState>>,
+        Extension(user): Extension<AuthUser>,
+        RoutePath((_storage_id, job_id)): RoutePath<(Uuid, Uuid)>,
+    ) -> Result<Response, (StatusCode, String)> {
+        let status = FilesService::new(&state.db, state.tx.clone())
+            .get_upload_job_status(job_id, &user)
+            .await
+            .map_err(<(StatusCode, String)>::from)?;
+
+        Ok(Json(status).into_response())
+    }
+
+    /// Starts a resumable, tus-style upload: the client later `PATCH`es
+    /// bytes onto the returned session at whatever pace/connection it can
+    /// manage, instead of streaming the whole file in one request.
+    async fn create_upload_session(
+        State(state): State<Arc<AppState>>,
+        Extension(user): Extension<AuthUser>,
+        RoutePath(storage_id): RoutePath<Uuid>,
+        Json(params): Json<CreateUploadSessionParams>,
+    ) -> Result<Json<UploadSessionCreated>, (StatusCode, String)> {
+        let temp_dir = &state.config.temp_dir;
+        tokio::fs::create_dir_all(temp_dir).await.map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to create temp directory: {}", e),
+            )
+        })?;
+        let temp_file_path = temp_dir.join(format!("upload_{}.tmp", Uuid::new_v4()));
+        tokio::fs::File::create(&temp_file_path).await.map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to create temp file: {}", e),
+            )
+        })?;
+
+        let id = FilesService::new(&state.db, state.tx.clone())
+            .create_upload_session(storage_id, params.path, params.size, temp_file_path, &user)
+            .await
+            .map_err(<(StatusCode, String)>::from)?;
+
+        Ok(Json(UploadSessionCreated { id }))
+    }
+
+    /// `HEAD /upload_session/{id}` — reports how many bytes the server has
+    /// stored so far, so a reconnecting client knows where to resume from.
+    async fn upload_session_status(
+        State(state): State<Arc<AppState>>,
+        Extension(user): Extension<AuthUser>,
+        RoutePath((storage_id, session_id)): RoutePath<(Uuid, Uuid)>,
+    ) -> Result<impl IntoResponse, (StatusCode, String)> {
+        let offset = FilesService::new(&state.db, state.tx.clone())
+            .get_upload_session_offset(storage_id, session_id, &user)
+            .await
+            .map_err(<(StatusCode, String)>::from)?;
+
+        Ok(AppendHeaders([(UPLOAD_OFFSET, offset.to_string())]))
+    }
+
+    /// `PATCH /upload_session/{id}` — appends the request body at the
+    /// offset given by the `Upload-Offset` header, finalizing the upload
+    /// once every declared byte has arrived.
+    async fn upload_session_patch(
+        State(state): State<Arc<AppState>>,
+        Extension(user): Extension<AuthUser>,
+        RoutePath((storage_id, session_id)): RoutePath<(Uuid, Uuid)>,
+        headers: HeaderMap,
+        body: axum::body::Body,
+    ) -> Result<StatusCode, (StatusCode, String)> {
+        let offset: i64 = headers
+            .get(UPLOAD_OFFSET)
+            .and_then(|value| value.to_str().ok())
+            .ok_or((
+                StatusCode::BAD_REQUEST,
+                "Upload-Offset header is required".to_owned(),
+            ))?
+            .parse()
+            .map_err(|_| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    "Upload-Offset header must be an integer".to_owned(),
+                )
+            })?;
+
+        // Stream the body straight to the session's temp file instead of
+        // buffering it with the `Bytes` extractor - PATCH is exactly the
+        // large-blob path this series is meant to keep memory-bounded.
+        let service = FilesService::new(&state.db, state.tx.clone());
+        let is_complete = service
+            .append_to_upload_session(storage_id, session_id, offset, body.into_data_stream(), &user)
+            .await
+            .map_err(|e| match e {
+                PentaractError::UploadOffsetMismatch => (
+                    StatusCode::CONFLICT,
+                    "Upload-Offset does not match the session's current offset".to_owned(),
+                ),
+                e => <(StatusCode, String)>::from(e),
+            })?;
+
+        if is_complete {
+            service
+                .finalize_upload_session(storage_id, session_id, &user)
+                .await
+                .map_err(<(StatusCode, String)>::from)?;
+        }
+
+        Ok(StatusCode::NO_CONTENT)
     }
 
     async fn create_folder(
@@ -288,37 +499,131 @@ impl FilesRouter {
             .map(|p| p.to_string())
     }
 
+    /// Size, in bytes, of the chunks pulled from the backing store while
+    /// streaming a download to the client.
+    const DOWNLOAD_CHUNK_SIZE: usize = 1024 * 1024;
+
     async fn download(
         state: Arc<AppState>,
         user: AuthUser,
         storage_id: Uuid,
         path: &str,
+        range: Option<String>,
+        inline: bool,
     ) -> Result<Response, (StatusCode, String)> {
-        FilesService::new(&state.db, state.tx.clone())
-            .download(path, storage_id, &user)
+        let service = FilesService::new(&state.db, state.tx.clone());
+
+        let (total, stored_content_type) = service
+            .file_meta(path, storage_id, &user)
             .await
-            .map(|data| {
-                let filename = Path::new(&path)
-                    .file_name()
-                    .map(|name| name.to_str().unwrap_or_default())
-                    .unwrap_or("unnamed.bin");
-                let content_type = mime_guess::from_path(filename)
-                    .first_or_octet_stream()
-                    .to_string();
-                let bytes = Bytes::from(data);
-                let body = Full::new(bytes);
-
-                let headers = AppendHeaders([
-                    (header::CONTENT_TYPE, content_type),
-                    (
-                        header::CONTENT_DISPOSITION,
-                        format!("attachment; filename=\"{filename}\""),
-                    ),
-                ]);
-
-                (headers, body).into_response()
-            })
-            .map_err(|e| <(StatusCode, String)>::from(e))
+            .map_err(<(StatusCode, String)>::from)?;
+
+        let (start, end, status, len) = match range.as_deref().map(|h| Self::parse_range(h, total)) {
+            Some(Ok((start, end))) => (start, end, StatusCode::PARTIAL_CONTENT, end + 1 - start),
+            Some(Err(RangeError::Unsupported)) => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Only a single byte range is supported".to_owned(),
+                ))
+            }
+            Some(Err(RangeError::Unsatisfiable)) => {
+                let headers = AppendHeaders([(header::CONTENT_RANGE, format!("bytes */{total}"))]);
+                return Ok((StatusCode::RANGE_NOT_SATISFIABLE, headers).into_response());
+            }
+            // No Range header: serve the whole file. `total` rather than
+            // `end - start + 1` so a 0-byte file reports `Content-Length: 0`
+            // instead of asking for one byte past EOF.
+            None => (0, total.saturating_sub(1), StatusCode::OK, total),
+        };
+
+        let mut reader = service
+            .download_range(path, storage_id, start, len, &user)
+            .await
+            .map_err(<(StatusCode, String)>::from)?;
+
+        let (tx, rx) = tokio::sync::mpsc::channel::<std::io::Result<Bytes>>(4);
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; Self::DOWNLOAD_CHUNK_SIZE];
+            loop {
+                match reader.read(&mut buf).await {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        if tx.send(Ok(Bytes::copy_from_slice(&buf[..n]))).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        break;
+                    }
+                }
+            }
+        });
+        let body = StreamBody::new(ReceiverStream::new(rx));
+
+        let filename = Path::new(&path)
+            .file_name()
+            .map(|name| name.to_str().unwrap_or_default())
+            .unwrap_or("unnamed.bin");
+        let content_type = stored_content_type.unwrap_or_else(|| {
+            mime_guess::from_path(filename)
+                .first_or_octet_stream()
+                .to_string()
+        });
+        let disposition = if inline { "inline" } else { "attachment" };
+
+        let mut headers = vec![
+            (header::CONTENT_TYPE, content_type),
+            (header::ACCEPT_RANGES, "bytes".to_owned()),
+            (header::CONTENT_LENGTH, len.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("{disposition}; filename=\"{filename}\""),
+            ),
+        ];
+        if status == StatusCode::PARTIAL_CONTENT {
+            headers.push((header::CONTENT_RANGE, format!("bytes {start}-{end}/{total}")));
+        }
+
+        Ok((status, AppendHeaders(headers), body).into_response())
+    }
+
+    /// Parses a `Range: bytes=start-end` header against a known total size,
+    /// returning the inclusive `(start, end)` byte interval to serve.
+    fn parse_range(header: &str, total: u64) -> Result<(u64, u64), RangeError> {
+        let spec = header.strip_prefix("bytes=").ok_or(RangeError::Unsupported)?;
+        if spec.contains(',') {
+            // Multiple ranges in one request aren't supported.
+            return Err(RangeError::Unsupported);
+        }
+        if total == 0 {
+            return Err(RangeError::Unsatisfiable);
+        }
+
+        let (raw_start, raw_end) = spec.split_once('-').ok_or(RangeError::Unsupported)?;
+        let (start, end) = if raw_start.is_empty() {
+            // Suffix range, e.g. `bytes=-500` meaning the last 500 bytes.
+            let suffix_len: u64 = raw_end.parse().map_err(|_| RangeError::Unsupported)?;
+            if suffix_len == 0 {
+                return Err(RangeError::Unsatisfiable);
+            }
+            let suffix_len = suffix_len.min(total);
+            (total - suffix_len, total - 1)
+        } else {
+            let start: u64 = raw_start.parse().map_err(|_| RangeError::Unsupported)?;
+            let end = if raw_end.is_empty() {
+                total - 1
+            } else {
+                raw_end.parse().map_err(|_| RangeError::Unsupported)?
+            };
+            (start, end)
+        };
+
+        if start >= total || start > end {
+            return Err(RangeError::Unsatisfiable);
+        }
+
+        Ok((start, end.min(total - 1)))
     }
 
     ///
@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Everything needed to create an `InFile` in one call, used by
+/// `upload_to` (the variant that takes the full destination path up
+/// front, unlike `upload` which joins `path` + the multipart filename).
+#[derive(Debug, Clone)]
+pub struct InFileSchema {
+    pub storage_id: Uuid,
+    pub path: String,
+    pub size: i64,
+    pub temp_file_path: PathBuf,
+    pub content_digest: String,
+    pub content_type: Option<String>,
+}
+
+impl InFileSchema {
+    pub fn new(
+        storage_id: Uuid,
+        path: String,
+        size: i64,
+        temp_file_path: PathBuf,
+        content_digest: String,
+        content_type: Option<String>,
+    ) -> Self {
+        Self { storage_id, path, size, temp_file_path, content_digest, content_type }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InFolderSchema {
+    pub storage_id: Uuid,
+    pub path: String,
+    pub folder_name: String,
+}
+
+impl InFolderSchema {
+    pub fn new(storage_id: Uuid, path: String, folder_name: String) -> Self {
+        Self { storage_id, path, folder_name }
+    }
+}
+
+/// Query string accepted by the `/*path` dynamic GET route, shared across
+/// its `tree`/`download`/`search` sub-handlers.
+#[derive(Debug, Deserialize, Default)]
+pub struct SearchQuery {
+    pub search_path: Option<String>,
+    /// `?disposition=inline` on `download` to ask for an inline
+    /// `Content-Disposition` instead of the default `attachment`.
+    pub disposition: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadParams {
+    pub path: String,
+    pub folder_name: String,
+}
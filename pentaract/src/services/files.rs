@@ -0,0 +1,632 @@
+use std::path::PathBuf;
+
+use sqlx::PgPool;
+use tokio::{
+    fs::File as TempFile,
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom},
+};
+use tokio_util::bytes::Bytes;
+use uuid::Uuid;
+
+use crate::{
+    common::jwt_manager::AuthUser,
+    errors::{PentaractError, PentaractResult},
+    models::{
+        files::{FsEntry, InFile},
+        upload_jobs::{UploadJob, UploadJobStatus},
+        upload_sessions::UploadSession,
+    },
+    schemas::files::{InFileSchema, InFolderSchema},
+};
+
+/// Upload attempts a background job retries against the backing store
+/// before giving up and marking itself `Failed`.
+const UPLOAD_JOB_MAX_ATTEMPTS: u32 = 5;
+
+/// How long an upload session may sit untouched before its temp file and
+/// row are considered abandoned and swept up.
+const SESSION_TTL_SECS: i64 = 24 * 60 * 60;
+
+/// Business logic backing `routers::files::FilesRouter`. Holds a DB handle
+/// plus the channel the rest of the storage-worker pool listens on; cheap to
+/// construct, so every handler makes a fresh one per request.
+#[derive(Clone)]
+pub struct FilesService {
+    db: PgPool,
+    tx: tokio::sync::mpsc::UnboundedSender<()>,
+}
+
+impl FilesService {
+    pub fn new(db: &PgPool, tx: tokio::sync::mpsc::UnboundedSender<()>) -> Self {
+        Self { db: db.clone(), tx }
+    }
+
+    fn blob_path(&self, content_digest: &str) -> PathBuf {
+        PathBuf::from("storage").join(content_digest)
+    }
+
+    pub async fn list_dir(
+        &self,
+        storage_id: Uuid,
+        path: &str,
+        _user: &AuthUser,
+    ) -> PentaractResult<Vec<FsEntry>> {
+        let entries = sqlx::query_as::<_, (String, i64)>(
+            "SELECT path, size FROM files WHERE storage_id = $1 AND parent_path = $2",
+        )
+        .bind(storage_id)
+        .bind(path)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(entries
+            .into_iter()
+            .map(|(path, size)| FsEntry { name: path, is_dir: size < 0, size: size.max(0) })
+            .collect())
+    }
+
+    pub async fn search(
+        &self,
+        storage_id: Uuid,
+        path: &str,
+        search_path: &str,
+        _user: &AuthUser,
+    ) -> PentaractResult<Vec<FsEntry>> {
+        let pattern = format!("{path}%{search_path}%");
+        let entries = sqlx::query_as::<_, (String, i64)>(
+            "SELECT path, size FROM files WHERE storage_id = $1 AND path LIKE $2",
+        )
+        .bind(storage_id)
+        .bind(pattern)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(entries
+            .into_iter()
+            .map(|(path, size)| FsEntry { name: path, is_dir: size < 0, size: size.max(0) })
+            .collect())
+    }
+
+    pub async fn create_folder(
+        &self,
+        in_schema: InFolderSchema,
+        _user: &AuthUser,
+    ) -> PentaractResult<()> {
+        let full_path = format!("{}/{}", in_schema.path.trim_end_matches('/'), in_schema.folder_name);
+        sqlx::query(
+            "INSERT INTO files (storage_id, path, parent_path, size, content_digest)
+             VALUES ($1, $2, $3, -1, '')",
+        )
+        .bind(in_schema.storage_id)
+        .bind(full_path)
+        .bind(in_schema.path)
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Size and stored MIME type of a file, used by `download` to build the
+    /// `Content-Range`/`Content-Type` headers without reading any bytes.
+    pub async fn file_meta(
+        &self,
+        path: &str,
+        storage_id: Uuid,
+        _user: &AuthUser,
+    ) -> PentaractResult<(u64, Option<String>)> {
+        let row = sqlx::query_as::<_, (i64, Option<String>)>(
+            "SELECT size, content_type FROM files WHERE storage_id = $1 AND path = $2",
+        )
+        .bind(storage_id)
+        .bind(path)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| PentaractError::NotFound(path.to_owned()))?;
+
+        Ok((row.0.max(0) as u64, row.1))
+    }
+
+    /// Opens the backing blob for `path` seeked to `offset`, ready to be
+    /// read for up to `len` bytes. Memory use is bounded by whatever the
+    /// caller reads into, not by file size. The handle is wrapped in
+    /// `AsyncReadExt::take(len)`, so it reports EOF after `len` bytes.
+    pub async fn download_range(
+        &self,
+        path: &str,
+        storage_id: Uuid,
+        offset: u64,
+        len: u64,
+        _user: &AuthUser,
+    ) -> PentaractResult<tokio::io::Take<TempFile>> {
+        let content_digest = sqlx::query_scalar::<_, String>(
+            "SELECT content_digest FROM files WHERE storage_id = $1 AND path = $2",
+        )
+        .bind(storage_id)
+        .bind(path)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| PentaractError::NotFound(path.to_owned()))?;
+
+        let mut file = TempFile::open(self.blob_path(&content_digest)).await?;
+        file.seek(SeekFrom::Start(offset)).await?;
+        Ok(file.take(len))
+    }
+
+    pub async fn upload_anyway(
+        &self,
+        in_file: InFile,
+        temp_file_path: PathBuf,
+        user: &AuthUser,
+    ) -> PentaractResult<()> {
+        self.store_content(in_file.storage_id, &in_file.content_digest, &temp_file_path, in_file.size)
+            .await?;
+        self.insert_file_row(&in_file, user).await
+    }
+
+    pub async fn upload_to(&self, in_schema: InFileSchema, user: &AuthUser) -> PentaractResult<()> {
+        let in_file = InFile::new(
+            in_schema.path,
+            in_schema.size,
+            in_schema.storage_id,
+            in_schema.content_digest,
+            in_schema.content_type,
+        );
+        self.store_content(in_file.storage_id, &in_file.content_digest, &in_schema.temp_file_path, in_file.size)
+            .await?;
+        self.insert_file_row(&in_file, user).await
+    }
+
+    /// Registers `temp_file_path`'s content under `content_digest` in the
+    /// content index. If identical bytes were already uploaded to this
+    /// storage, bumps that entry's `ref_count` and drops the temp file
+    /// instead of writing a second copy to the backing store; otherwise
+    /// this upload becomes the index's first (and so far only) reference.
+    async fn store_content(
+        &self,
+        storage_id: Uuid,
+        content_digest: &str,
+        temp_file_path: &PathBuf,
+        size: i64,
+    ) -> PentaractResult<()> {
+        // A retried upload job can call this again after an earlier attempt
+        // already consumed temp_file_path (renamed it into the blob store,
+        // or removed it on a dedup hit) but then failed further down in
+        // insert_file_row. That earlier attempt already did its one
+        // legitimate ref_count increment, so finding the temp file gone
+        // means "already stored" - re-running the lookup below would bump
+        // ref_count a second time for the same logical upload.
+        if tokio::fs::metadata(temp_file_path).await.is_err() {
+            return Ok(());
+        }
+
+        let existing = sqlx::query_scalar::<_, Uuid>(
+            "UPDATE content_index SET ref_count = ref_count + 1
+             WHERE storage_id = $1 AND content_digest = $2
+             RETURNING id",
+        )
+        .bind(storage_id)
+        .bind(content_digest)
+        .fetch_optional(&self.db)
+        .await?;
+
+        if existing.is_some() {
+            // Identical content already lives in the backing store under
+            // this digest - skip re-uploading the bytes entirely.
+            tokio::fs::remove_file(temp_file_path).await?;
+            return Ok(());
+        }
+
+        let blob_path = self.blob_path(content_digest);
+        if let Some(parent) = blob_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(temp_file_path, &blob_path).await?;
+
+        sqlx::query(
+            "INSERT INTO content_index (id, storage_id, content_digest, size, ref_count)
+             VALUES ($1, $2, $3, $4, 1)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(storage_id)
+        .bind(content_digest)
+        .bind(size)
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_file_row(&self, in_file: &InFile, _user: &AuthUser) -> PentaractResult<()> {
+        let parent_path = parent_path_of(&in_file.path);
+
+        // Overwriting an existing path drops that path's reference to
+        // whatever digest it pointed at before - release it the same way
+        // `delete()` would, or the old blob's ref_count never comes back
+        // down and it can never be purged.
+        let previous_digest = sqlx::query_scalar::<_, String>(
+            "SELECT content_digest FROM files WHERE storage_id = $1 AND path = $2",
+        )
+        .bind(in_file.storage_id)
+        .bind(&in_file.path)
+        .fetch_optional(&self.db)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO files (storage_id, path, parent_path, size, content_digest, content_type)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (storage_id, path) DO UPDATE
+               SET size = excluded.size,
+                   content_digest = excluded.content_digest,
+                   content_type = excluded.content_type",
+        )
+        .bind(in_file.storage_id)
+        .bind(&in_file.path)
+        .bind(parent_path)
+        .bind(in_file.size)
+        .bind(&in_file.content_digest)
+        .bind(&in_file.content_type)
+        .execute(&self.db)
+        .await?;
+
+        // Released even when the digest is unchanged: `store_content` has
+        // already counted this upload as a new reference to it.
+        if let Some(previous_digest) = previous_digest {
+            self.release_content(in_file.storage_id, &previous_digest).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, path: &str, storage_id: Uuid, _user: &AuthUser) -> PentaractResult<()> {
+        let content_digest = sqlx::query_scalar::<_, String>(
+            "DELETE FROM files WHERE storage_id = $1 AND path = $2 RETURNING content_digest",
+        )
+        .bind(storage_id)
+        .bind(path)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| PentaractError::NotFound(path.to_owned()))?;
+
+        self.release_content(storage_id, &content_digest).await
+    }
+
+    /// Decrements `content_digest`'s `ref_count` in the content index, and
+    /// purges the index row and backing blob once it reaches zero. Shared by
+    /// `delete()` and `insert_file_row()` (the latter when an overwrite
+    /// moves a path off its previous digest) since both are "a path stopped
+    /// pointing at this content".
+    async fn release_content(&self, storage_id: Uuid, content_digest: &str) -> PentaractResult<()> {
+        // Folder rows carry no content of their own.
+        if content_digest.is_empty() {
+            return Ok(());
+        }
+
+        let ref_count = sqlx::query_scalar::<_, i64>(
+            "UPDATE content_index SET ref_count = ref_count - 1
+             WHERE storage_id = $1 AND content_digest = $2
+             RETURNING ref_count",
+        )
+        .bind(storage_id)
+        .bind(content_digest)
+        .fetch_optional(&self.db)
+        .await?
+        .unwrap_or(0);
+
+        if ref_count <= 0 {
+            sqlx::query("DELETE FROM content_index WHERE storage_id = $1 AND content_digest = $2")
+                .bind(storage_id)
+                .bind(content_digest)
+                .execute(&self.db)
+                .await?;
+            let _ = tokio::fs::remove_file(self.blob_path(content_digest)).await;
+        }
+
+        Ok(())
+    }
+
+    // --- Resumable (tus-style) upload sessions ---------------------------
+
+    pub async fn create_upload_session(
+        &self,
+        storage_id: Uuid,
+        path: String,
+        total_size: i64,
+        temp_file_path: PathBuf,
+        user: &AuthUser,
+    ) -> PentaractResult<Uuid> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO upload_sessions
+               (id, storage_id, path, total_size, offset_bytes, temp_file_path, owner_id, updated_at)
+             VALUES ($1, $2, $3, $4, 0, $5, $6, now())",
+        )
+        .bind(id)
+        .bind(storage_id)
+        .bind(path)
+        .bind(total_size)
+        .bind(temp_file_path.to_string_lossy().to_string())
+        .bind(user.id)
+        .execute(&self.db)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Looks up a session, scoped to `storage_id` and the requesting user
+    /// the same way every other `FilesService` query scopes by
+    /// `storage_id` - and additionally checks `owner_id`, since a session
+    /// is tied to whoever started it rather than shared across a storage's
+    /// users. Both mismatches report `NotFound` rather than a distinct
+    /// "forbidden" so a session id doesn't become a way to probe which ids
+    /// exist in storages the caller can't touch.
+    async fn get_session(&self, storage_id: Uuid, session_id: Uuid, user: &AuthUser) -> PentaractResult<UploadSession> {
+        let session = sqlx::query_as::<_, UploadSession>(
+            "SELECT id, storage_id, path, total_size, offset_bytes AS offset, temp_file_path, owner_id
+             FROM upload_sessions WHERE id = $1 AND storage_id = $2",
+        )
+        .bind(session_id)
+        .bind(storage_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| PentaractError::NotFound(session_id.to_string()))?;
+
+        if session.owner_id != user.id {
+            return Err(PentaractError::NotFound(session_id.to_string()));
+        }
+
+        Ok(session)
+    }
+
+    pub async fn get_upload_session_offset(
+        &self,
+        storage_id: Uuid,
+        session_id: Uuid,
+        user: &AuthUser,
+    ) -> PentaractResult<i64> {
+        Ok(self.get_session(storage_id, session_id, user).await?.offset)
+    }
+
+    /// Appends `body` to the session's temp file at `offset`, streaming it
+    /// straight to disk rather than buffering it, and returns whether the
+    /// session is now complete. Conflicting offsets (a retry that disagrees
+    /// with what the server has stored) are rejected so the client can
+    /// re-sync with a `HEAD` instead of silently corrupting the file.
+    pub async fn append_to_upload_session(
+        &self,
+        storage_id: Uuid,
+        session_id: Uuid,
+        offset: i64,
+        mut body: impl futures_util::Stream<Item = Result<Bytes, axum::Error>> + Unpin,
+        user: &AuthUser,
+    ) -> PentaractResult<bool> {
+        use futures_util::StreamExt;
+
+        let session = self.get_session(storage_id, session_id, user).await?;
+        if offset != session.offset {
+            return Err(PentaractError::UploadOffsetMismatch);
+        }
+
+        let mut temp_file = TempFile::options()
+            .write(true)
+            .open(session.temp_file_path())
+            .await?;
+        temp_file.seek(SeekFrom::Start(offset as u64)).await?;
+
+        let mut written = 0i64;
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.map_err(|e| PentaractError::StorageBackend(e.to_string()))?;
+            temp_file.write_all(&chunk).await?;
+            written += chunk.len() as i64;
+        }
+        temp_file.flush().await?;
+
+        let new_offset = offset + written;
+        sqlx::query("UPDATE upload_sessions SET offset_bytes = $1, updated_at = now() WHERE id = $2")
+            .bind(new_offset)
+            .bind(session_id)
+            .execute(&self.db)
+            .await?;
+
+        Ok(new_offset >= session.total_size)
+    }
+
+    pub async fn finalize_upload_session(
+        &self,
+        storage_id: Uuid,
+        session_id: Uuid,
+        user: &AuthUser,
+    ) -> PentaractResult<()> {
+        let session = self.get_session(storage_id, session_id, user).await?;
+        let content_digest = Self::hash_file(&session.temp_file_path()).await?;
+        let in_file = InFile::new(session.path.clone(), session.total_size, session.storage_id, content_digest, None);
+
+        self.upload_anyway(in_file, session.temp_file_path(), user).await?;
+
+        sqlx::query("DELETE FROM upload_sessions WHERE id = $1")
+            .bind(session_id)
+            .execute(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Deletes sessions (and their temp files) that haven't been touched in
+    /// `SESSION_TTL_SECS`, so a client that disappears mid-upload doesn't
+    /// leak disk space forever. Meant to be run periodically by the app's
+    /// background scheduler.
+    pub async fn cleanup_abandoned_upload_sessions(&self) -> PentaractResult<()> {
+        let abandoned = sqlx::query_as::<_, UploadSession>(
+            "SELECT id, storage_id, path, total_size, offset_bytes AS offset, temp_file_path, owner_id
+             FROM upload_sessions
+             WHERE updated_at < now() - ($1 || ' seconds')::interval",
+        )
+        .bind(SESSION_TTL_SECS.to_string())
+        .fetch_all(&self.db)
+        .await?;
+
+        for session in abandoned {
+            let _ = tokio::fs::remove_file(session.temp_file_path()).await;
+            sqlx::query("DELETE FROM upload_sessions WHERE id = $1")
+                .bind(session.id)
+                .execute(&self.db)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Spawns the periodic sweep of abandoned upload sessions. Call this
+    /// once at app startup (alongside the rest of the background workers);
+    /// it runs for the lifetime of the process.
+    pub fn spawn_session_cleanup_task(db: PgPool) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+            let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+            let service = FilesService { db, tx };
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.cleanup_abandoned_upload_sessions().await {
+                    tracing::warn!("failed to clean up abandoned upload sessions: {e}");
+                }
+            }
+        })
+    }
+
+    /// Hashes a file already on disk the same way `upload`/`upload_to` hash
+    /// the multipart stream as it arrives, for callers (like resumable
+    /// sessions) that only have the finished file rather than a live
+    /// stream of chunks to feed an incremental hasher.
+    async fn hash_file(path: &PathBuf) -> PentaractResult<String> {
+        let mut file = TempFile::open(path).await?;
+        let mut hasher = blake3::Hasher::new();
+        let mut buf = vec![0u8; 1024 * 1024];
+        loop {
+            let n = file.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        Ok(hasher.finalize().to_hex().to_string())
+    }
+
+    // --- Async upload jobs ------------------------------------------------
+
+    pub async fn enqueue_upload_job(
+        &self,
+        in_file: InFile,
+        temp_file_path: PathBuf,
+        user: &AuthUser,
+    ) -> PentaractResult<Uuid> {
+        let id = self.insert_job_row().await?;
+
+        let service = self.clone();
+        let user = user.clone();
+        tokio::spawn(async move {
+            service.run_upload_job(id, in_file, temp_file_path, user).await;
+        });
+
+        Ok(id)
+    }
+
+    pub async fn enqueue_upload_to_job(&self, in_schema: InFileSchema, user: &AuthUser) -> PentaractResult<Uuid> {
+        let id = self.insert_job_row().await?;
+        let temp_file_path = in_schema.temp_file_path.clone();
+        let in_file = InFile::new(
+            in_schema.path,
+            in_schema.size,
+            in_schema.storage_id,
+            in_schema.content_digest,
+            in_schema.content_type,
+        );
+
+        let service = self.clone();
+        let user = user.clone();
+        tokio::spawn(async move {
+            service.run_upload_job(id, in_file, temp_file_path, user).await;
+        });
+
+        Ok(id)
+    }
+
+    async fn insert_job_row(&self) -> PentaractResult<Uuid> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO upload_jobs (id, status, progress_bytes, error)
+             VALUES ($1, 'queued', 0, NULL)",
+        )
+        .bind(id)
+        .execute(&self.db)
+        .await?;
+        Ok(id)
+    }
+
+    pub async fn get_upload_job_status(&self, job_id: Uuid, _user: &AuthUser) -> PentaractResult<UploadJob> {
+        sqlx::query_as::<_, UploadJob>(
+            "SELECT id, status, progress_bytes, error FROM upload_jobs WHERE id = $1",
+        )
+        .bind(job_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| PentaractError::NotFound(job_id.to_string()))
+    }
+
+    async fn set_job_status(
+        &self,
+        job_id: Uuid,
+        status: UploadJobStatus,
+        progress_bytes: i64,
+        error: Option<&str>,
+    ) {
+        let result = sqlx::query(
+            "UPDATE upload_jobs SET status = $1, progress_bytes = $2, error = $3 WHERE id = $4",
+        )
+        .bind(status.as_str())
+        .bind(progress_bytes)
+        .bind(error)
+        .bind(job_id)
+        .execute(&self.db)
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!("failed to update upload job {job_id}: {e}");
+        }
+    }
+
+    /// Runs in the background once a job is enqueued: pushes the staged
+    /// temp file to the backing store, retrying transient failures with
+    /// exponential backoff before giving up and marking the job `Failed`.
+    async fn run_upload_job(&self, job_id: Uuid, in_file: InFile, temp_file_path: PathBuf, user: AuthUser) {
+        self.set_job_status(job_id, UploadJobStatus::Uploading, 0, None).await;
+
+        let mut delay = std::time::Duration::from_millis(500);
+        let mut last_error = String::new();
+
+        for attempt in 1..=UPLOAD_JOB_MAX_ATTEMPTS {
+            match self.upload_anyway(in_file.clone(), temp_file_path.clone(), &user).await {
+                Ok(()) => {
+                    self.set_job_status(job_id, UploadJobStatus::Done, in_file.size, None).await;
+                    return;
+                }
+                Err(e) => {
+                    last_error = e.to_string();
+                    if attempt == UPLOAD_JOB_MAX_ATTEMPTS {
+                        break;
+                    }
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+            }
+        }
+
+        let _ = tokio::fs::remove_file(&temp_file_path).await;
+        self.set_job_status(job_id, UploadJobStatus::Failed, 0, Some(&last_error)).await;
+    }
+}
+
+fn parent_path_of(path: &str) -> String {
+    std::path::Path::new(path)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
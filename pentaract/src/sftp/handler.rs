@@ -0,0 +1,312 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use russh_sftp::protocol::{
+    Attrs, Data, File, FileAttributes, Handle, Name, OpenFlags, Status, StatusCode, Version,
+};
+use tokio::{
+    fs::File as TempFile,
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom},
+};
+use uuid::Uuid;
+
+use crate::{
+    common::{jwt_manager::AuthUser, routing::app_state::AppState},
+    errors::PentaractError,
+    models::files::InFile,
+    schemas::files::InFolderSchema,
+    services::files::FilesService,
+};
+
+/// A handle an SFTP client is currently holding open, either for reading
+/// (backed directly by the storage) or writing (backed by a local temp file,
+/// the same way `upload`/`upload_to` stage uploads before handing them to
+/// [`FilesService::upload_anyway`]).
+enum OpenHandle {
+    Read { path: String, size: u64 },
+    Write {
+        path: String,
+        temp_file_path: PathBuf,
+        temp_file: TempFile,
+        size: i64,
+        /// Fed every chunk as it's written, in order, mirroring how the
+        /// HTTP `upload`/`upload_to` handlers hash multipart chunks as they
+        /// arrive. Only correct if the client writes sequentially from
+        /// offset 0, so `write()` checks `offset == hashed_len` and rejects
+        /// anything else rather than silently hashing the wrong bytes.
+        hasher: blake3::Hasher,
+        hashed_len: u64,
+    },
+}
+
+/// Translates the SFTP protocol operations of a single connected session
+/// into [`FilesService`] calls against one storage, the same calls
+/// `routers::files::FilesRouter` makes on behalf of the HTTP API. Every
+/// connected user is mapped to a virtual root under their storage, matching
+/// the `/*path` scheme the HTTP router uses.
+pub struct SftpHandler {
+    state: Arc<AppState>,
+    user: AuthUser,
+    storage_id: Uuid,
+    handles: HashMap<String, OpenHandle>,
+    next_handle_id: u64,
+}
+
+impl SftpHandler {
+    pub fn new(state: Arc<AppState>, user: AuthUser, storage_id: Uuid) -> Self {
+        Self {
+            state,
+            user,
+            storage_id,
+            handles: HashMap::new(),
+            next_handle_id: 0,
+        }
+    }
+
+    fn service(&self) -> FilesService {
+        FilesService::new(&self.state.db, self.state.tx.clone())
+    }
+
+    fn new_handle_id(&mut self) -> String {
+        self.next_handle_id += 1;
+        self.next_handle_id.to_string()
+    }
+
+    fn status_of(error: PentaractError) -> StatusCode {
+        match error {
+            PentaractError::NotFound(_) => StatusCode::NoSuchFile,
+            PentaractError::PermissionDenied => StatusCode::PermissionDenied,
+            _ => StatusCode::Failure,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl russh_sftp::server::Handler for SftpHandler {
+    type Error = StatusCode;
+
+    fn unimplemented(&self) -> Self::Error {
+        StatusCode::OpUnsupported
+    }
+
+    async fn init(
+        &mut self,
+        version: u32,
+        _extensions: HashMap<String, String>,
+    ) -> Result<Version, Self::Error> {
+        Ok(Version::new_with_extensions(version, HashMap::new()))
+    }
+
+    async fn opendir(&mut self, id: u32, path: String) -> Result<Handle, Self::Error> {
+        let _ = self
+            .service()
+            .list_dir(self.storage_id, &path, &self.user)
+            .await
+            .map_err(Self::status_of)?;
+
+        let handle_id = self.new_handle_id();
+        self.handles.insert(
+            handle_id.clone(),
+            OpenHandle::Read { path, size: 0 },
+        );
+        Ok(Handle { id, handle: handle_id })
+    }
+
+    async fn readdir(&mut self, id: u32, handle: String) -> Result<Name, Self::Error> {
+        let path = match self.handles.remove(&handle) {
+            Some(OpenHandle::Read { path, .. }) => path,
+            _ => return Err(StatusCode::Failure),
+        };
+
+        let fs_layer = self
+            .service()
+            .list_dir(self.storage_id, &path, &self.user)
+            .await
+            .map_err(Self::status_of)?;
+
+        let files = fs_layer
+            .into_iter()
+            .map(|entry| File {
+                filename: entry.name,
+                longname: String::new(),
+                attrs: FileAttributes::default(),
+            })
+            .collect();
+
+        Ok(Name { id, files })
+    }
+
+    async fn open(
+        &mut self,
+        id: u32,
+        filename: String,
+        pflags: OpenFlags,
+        _attrs: FileAttributes,
+    ) -> Result<Handle, Self::Error> {
+        let handle_id = self.new_handle_id();
+
+        if pflags.contains(OpenFlags::WRITE) {
+            let temp_dir = &self.state.config.temp_dir;
+            tokio::fs::create_dir_all(temp_dir)
+                .await
+                .map_err(|_| StatusCode::Failure)?;
+            let temp_file_path = temp_dir.join(format!("sftp_{}.tmp", Uuid::new_v4()));
+            let temp_file = TempFile::create(&temp_file_path)
+                .await
+                .map_err(|_| StatusCode::Failure)?;
+
+            self.handles.insert(
+                handle_id.clone(),
+                OpenHandle::Write {
+                    path: filename,
+                    temp_file_path,
+                    temp_file,
+                    size: 0,
+                    hasher: blake3::Hasher::new(),
+                    hashed_len: 0,
+                },
+            );
+        } else {
+            let size = self
+                .service()
+                .file_meta(&filename, self.storage_id, &self.user)
+                .await
+                .map_err(Self::status_of)?
+                .0;
+
+            self.handles
+                .insert(handle_id.clone(), OpenHandle::Read { path: filename, size });
+        }
+
+        Ok(Handle { id, handle: handle_id })
+    }
+
+    async fn read(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        len: u32,
+    ) -> Result<Data, Self::Error> {
+        let (path, size) = match self.handles.get(&handle) {
+            Some(OpenHandle::Read { path, size }) => (path.clone(), *size),
+            _ => return Err(StatusCode::Failure),
+        };
+
+        if offset >= size {
+            return Err(StatusCode::Eof);
+        }
+        let len = (len as u64).min(size - offset);
+
+        let mut reader = self
+            .service()
+            .download_range(&path, self.storage_id, offset, len, &self.user)
+            .await
+            .map_err(Self::status_of)?;
+
+        let mut data = vec![0u8; len as usize];
+        reader
+            .read_exact(&mut data)
+            .await
+            .map_err(|_| StatusCode::Failure)?;
+
+        Ok(Data { id, data })
+    }
+
+    async fn write(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        data: Vec<u8>,
+    ) -> Result<Status, Self::Error> {
+        let open_handle = self.handles.get_mut(&handle).ok_or(StatusCode::Failure)?;
+        let (temp_file, size, hasher, hashed_len) = match open_handle {
+            OpenHandle::Write { temp_file, size, hasher, hashed_len, .. } => {
+                (temp_file, size, hasher, hashed_len)
+            }
+            _ => return Err(StatusCode::Failure),
+        };
+
+        // The content digest is only meaningful if every byte goes through
+        // the hasher exactly once, in order - reject an out-of-order write
+        // (e.g. a client seeking to resume or patch a region it already
+        // sent) before it can desync the digest from the file's real
+        // content, rather than hashing the wrong bytes silently.
+        if offset != *hashed_len {
+            return Err(StatusCode::Failure);
+        }
+
+        temp_file
+            .seek(SeekFrom::Start(offset))
+            .await
+            .map_err(|_| StatusCode::Failure)?;
+        temp_file.write_all(&data).await.map_err(|_| StatusCode::Failure)?;
+        hasher.update(&data);
+        *hashed_len += data.len() as u64;
+        *size = (*size).max(offset as i64 + data.len() as i64);
+
+        Ok(Status { id, status_code: StatusCode::Ok, error_message: String::new(), language_tag: String::new() })
+    }
+
+    async fn close(&mut self, id: u32, handle: String) -> Result<Status, Self::Error> {
+        if let Some(OpenHandle::Write { path, temp_file_path, size, hasher, .. }) =
+            self.handles.remove(&handle)
+        {
+            let content_digest = hasher.finalize().to_hex().to_string();
+            let in_file = InFile::new(path, size, self.storage_id, content_digest, None);
+            let result = self
+                .service()
+                .upload_anyway(in_file, temp_file_path.clone(), &self.user)
+                .await;
+
+            if result.is_err() {
+                let _ = tokio::fs::remove_file(&temp_file_path).await;
+            }
+            result.map_err(Self::status_of)?;
+        }
+
+        Ok(Status { id, status_code: StatusCode::Ok, error_message: String::new(), language_tag: String::new() })
+    }
+
+    async fn mkdir(
+        &mut self,
+        id: u32,
+        path: String,
+        _attrs: FileAttributes,
+    ) -> Result<Status, Self::Error> {
+        let (parent, folder_name) = match path.rsplit_once('/') {
+            Some((parent, name)) => (parent.to_owned(), name.to_owned()),
+            None => (String::new(), path.clone()),
+        };
+
+        let in_schema = InFolderSchema::new(self.storage_id, parent, folder_name);
+        self.service()
+            .create_folder(in_schema, &self.user)
+            .await
+            .map_err(Self::status_of)?;
+
+        Ok(Status { id, status_code: StatusCode::Ok, error_message: String::new(), language_tag: String::new() })
+    }
+
+    async fn remove(&mut self, id: u32, path: String) -> Result<Status, Self::Error> {
+        self.service()
+            .delete(&path, self.storage_id, &self.user)
+            .await
+            .map_err(Self::status_of)?;
+
+        Ok(Status { id, status_code: StatusCode::Ok, error_message: String::new(), language_tag: String::new() })
+    }
+
+    async fn stat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+        let (size, _) = self
+            .service()
+            .file_meta(&path, self.storage_id, &self.user)
+            .await
+            .map_err(Self::status_of)?;
+
+        Ok(Attrs {
+            id,
+            attrs: FileAttributes { size: Some(size), ..Default::default() },
+        })
+    }
+}
@@ -0,0 +1,13 @@
+//! SFTP front-end for a storage.
+//!
+//! This mirrors `routers::files::FilesRouter`: instead of the bespoke
+//! multipart HTTP API, a connected SSH client gets a standard SFTP subsystem
+//! backed by the exact same [`FilesService`] calls the HTTP handlers use, so
+//! tools like `sftp`, `rsync` or a desktop file manager can mount a storage
+//! directly.
+
+mod handler;
+mod server;
+
+pub use handler::SftpHandler;
+pub use server::SftpServer;
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use russh::{
+    server::{Auth, Config, Msg, Server as _, Session},
+    Channel, ChannelId,
+};
+use russh_keys::key::KeyPair;
+use russh_sftp::server::run as run_sftp_channel;
+
+use crate::{
+    common::{jwt_manager::JwtManager, routing::app_state::AppState},
+    sftp::handler::SftpHandler,
+};
+
+/// SSH server exposing every storage over SFTP. A client authenticates the
+/// same way the HTTP API's `logged_in_required` middleware does — by
+/// presenting a JWT, here as the SSH password — then gets a virtual root
+/// scoped to the storage named by the username (`<storage_id>`).
+pub struct SftpServer {
+    state: Arc<AppState>,
+}
+
+impl SftpServer {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Binds `bind_addr` and serves the SFTP gateway until the process
+    /// exits. Meant to be `tokio::spawn`ed alongside the HTTP listener and
+    /// [`FilesService::spawn_session_cleanup_task`] at app startup.
+    ///
+    /// The host key is freshly generated per run rather than loaded from
+    /// disk, since there's no persisted-key-storage convention in this app
+    /// yet to hook into; clients will see the host key change across
+    /// restarts until one is added.
+    pub async fn serve(mut self, bind_addr: impl tokio::net::ToSocketAddrs + Send) -> anyhow::Result<()> {
+        let config = Arc::new(Config {
+            keys: vec![KeyPair::generate_ed25519().expect("ed25519 key generation")],
+            ..Default::default()
+        });
+
+        self.run_on_address(config, bind_addr).await?;
+        Ok(())
+    }
+}
+
+impl russh::server::Server for SftpServer {
+    type Handler = SftpSession;
+
+    fn new_client(&mut self, _addr: Option<std::net::SocketAddr>) -> Self::Handler {
+        SftpSession { state: self.state.clone(), user: None, storage_id: None }
+    }
+}
+
+pub struct SftpSession {
+    state: Arc<AppState>,
+    user: Option<crate::common::jwt_manager::AuthUser>,
+    storage_id: Option<uuid::Uuid>,
+}
+
+#[async_trait::async_trait]
+impl russh::server::Handler for SftpSession {
+    type Error = anyhow::Error;
+
+    async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+        let Ok(storage_id) = user.parse() else {
+            return Ok(Auth::Reject { proceed_with_methods: None });
+        };
+        let Ok(auth_user) = JwtManager::decode_access_token(password) else {
+            return Ok(Auth::Reject { proceed_with_methods: None });
+        };
+
+        self.user = Some(auth_user);
+        self.storage_id = Some(storage_id);
+        Ok(Auth::Accept)
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn subsystem_request(
+        &mut self,
+        channel_id: ChannelId,
+        name: &str,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if name != "sftp" {
+            return Ok(());
+        }
+        let (Some(user), Some(storage_id)) = (self.user.clone(), self.storage_id) else {
+            return Ok(());
+        };
+
+        let channel = session.channels.remove(&channel_id).expect("channel just opened");
+        let handler = SftpHandler::new(self.state.clone(), user, storage_id);
+        run_sftp_channel(channel.into_stream(), handler).await;
+
+        Ok(())
+    }
+}